@@ -0,0 +1,24 @@
+//! Runtime support for the [`dervy`](https://docs.rs/dervy) entity derive.
+//!
+//! This crate holds the items that the `dervy::Entity` derive references from
+//! its generated code. `dervy` re-exports it, so downstream users refer to
+//! everything here as `dervy::...` rather than depending on `dervy-core`
+//! directly.
+
+/// A domain entity that is identified by a value of type [`Id`](Entity::Id).
+///
+/// The `dervy::Entity` derive implements this trait for structs with a single
+/// identity field, returning a reference to that field (composite identities
+/// and enums get no impl). It gives callers a
+/// uniform way to fetch an entity's identity generically:
+///
+/// ```ignore
+/// fn lookup<E: dervy::Entity>(id: &E::Id) { /* ... */ }
+/// ```
+pub trait Entity {
+    /// The type identifying this entity.
+    type Id;
+
+    /// Borrow this entity's identity.
+    fn id(&self) -> &Self::Id;
+}
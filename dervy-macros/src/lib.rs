@@ -0,0 +1,567 @@
+//! Implementation of the `Entity` derive macro re-exported from `dervy`.
+//!
+//! This crate is not meant to be depended on directly: it is a
+//! `proc-macro = true` crate and so cannot export anything but the macro
+//! itself, which is why `dervy` (a plain library crate) re-exports it
+//! alongside the `dervy_core::Entity` trait the generated code implements.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Ident, Index, Member,
+    Type,
+};
+
+/// Derive [PartialEq], [Eq], and [Hash] for this entity type by considering
+/// the field annotated with `#[dervy(id)]`
+///
+/// See [`dervy::Entity`](https://docs.rs/dervy) for the full set of
+/// supported attributes and a runnable example. This crate can't depend on
+/// `dervy` itself (doing so would be circular), so the snippet below is
+/// illustrative only:
+///
+/// ```ignore
+/// #[derive(Clone, Debug, dervy::Entity)]
+/// struct MyEntity {
+///     #[dervy(id)]
+///     my_entity_id: i32,
+///     other_field: bool,
+///     // ...
+/// }
+/// ```
+#[proc_macro_derive(Entity, attributes(dervy))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let identity = find_id_field(&input.data, name);
+
+    // Carry the entity's generics onto every generated impl. Rather than bound
+    // every type parameter, we only require the identity type(s) to be
+    // comparable and hashable, so entities generic over non-identity payload
+    // types (and const generics) still derive cleanly.
+    let mut generics = input.generics.clone();
+    for id_ty in identity.id_types() {
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#id_ty: PartialEq + Eq + std::hash::Hash));
+        if identity.wants_ord() {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#id_ty: PartialOrd + Ord));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let eq_body = identity.eq_body();
+    let hash_body = identity.hash_body();
+    // When the entity has a single identity field we can also expose it through
+    // the `dervy::Entity` trait, optionally behind a strongly-typed newtype.
+    let entity_impl = identity.entity_trait_impl(
+        name,
+        &input.generics,
+        &impl_generics,
+        &ty_generics,
+        where_clause,
+    );
+    // With `#[dervy(id, ord)]` we additionally order entities by identity so
+    // they can key a `BTreeMap`/`BTreeSet`.
+    let ord_impls = identity.ord_impls(name, &impl_generics, &ty_generics, where_clause);
+
+    let expanded = quote! {
+        impl #impl_generics PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                #eq_body
+            }
+        }
+
+        impl #impl_generics Eq for #name #ty_generics #where_clause {}
+
+        impl #impl_generics std::hash::Hash for #name #ty_generics #where_clause {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                #hash_body
+            }
+        }
+
+        #entity_impl
+        #ord_impls
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// The identity of an entity: the member(s) whose values decide equality.
+///
+/// Structs (named or tuple) compare and hash an ordered set of members; enums
+/// compare identities only within matching variants and never across them,
+/// folding the discriminant into the hash alongside the active variant's id.
+enum Identity<'a> {
+    Struct(Vec<IdField<'a>>),
+    Enum(Vec<IdVariant<'a>>),
+}
+
+/// A single identity member of a struct (a named field or a tuple position).
+struct IdField<'a> {
+    member: Member,
+    ty: &'a Type,
+    newtype: Option<Ident>,
+    project: Option<Ident>,
+    ord: bool,
+}
+
+impl IdField<'_> {
+    /// The expression (rooted at some receiver) that reads this identity, e.g.
+    /// `key` or, with `project = uuid`, `key.uuid`. `eq`, `hash` and `cmp` all
+    /// build on top of it so the three stay consistent.
+    fn access(&self) -> proc_macro2::TokenStream {
+        let member = &self.member;
+        match &self.project {
+            Some(project) => quote!(#member.#project),
+            None => quote!(#member),
+        }
+    }
+}
+
+/// One variant of an enum entity and the member, if any, that identifies it.
+struct IdVariant<'a> {
+    ident: &'a Ident,
+    fields: &'a Fields,
+    id: Option<VariantId<'a>>,
+}
+
+/// The identity member within a single enum variant.
+struct VariantId<'a> {
+    member: Member,
+    ty: &'a Type,
+    project: Option<Ident>,
+}
+
+/// The options parsed from a field's `#[dervy(id, ..)]` attribute.
+struct IdOptions {
+    newtype: Option<Ident>,
+    project: Option<Ident>,
+    ord: bool,
+}
+
+impl<'a> Identity<'a> {
+    /// The identity type(s) that need `PartialEq + Eq + Hash` bounds.
+    ///
+    /// Fields using `project` are excluded: `eq`/`hash`/`cmp` only ever touch
+    /// the projected subfield (e.g. `self.key.uuid`), not the outer field's
+    /// type (`Key`), and there's no way to name the subfield's type from the
+    /// outer field's `syn::Type` alone. Bounding the wrapper type there would
+    /// be both wrong and exactly the friction `project` exists to avoid.
+    fn id_types(&self) -> Vec<&'a Type> {
+        match self {
+            Identity::Struct(fields) => fields
+                .iter()
+                .filter(|f| f.project.is_none())
+                .map(|f| f.ty)
+                .collect(),
+            Identity::Enum(variants) => variants
+                .iter()
+                .filter_map(|v| v.id.as_ref())
+                .filter(|id| id.project.is_none())
+                .map(|id| id.ty)
+                .collect(),
+        }
+    }
+
+    /// The body of the generated `PartialEq::eq`.
+    fn eq_body(&self) -> proc_macro2::TokenStream {
+        match self {
+            Identity::Struct(fields) => {
+                let accesses = fields.iter().map(IdField::access).collect::<Vec<_>>();
+                quote! { #(self.#accesses == other.#accesses)&&* }
+            }
+            Identity::Enum(variants) => {
+                let arms = variants.iter().map(|v| match &v.id {
+                    Some(id) => {
+                        let a = format_ident!("__a");
+                        let b = format_ident!("__b");
+                        let pat_a = bind_variant(v.ident, v.fields, &id.member, &a);
+                        let pat_b = bind_variant(v.ident, v.fields, &id.member, &b);
+                        let a = project_binding(&a, &id.project);
+                        let b = project_binding(&b, &id.project);
+                        quote! { (#pat_a, #pat_b) => #a == #b, }
+                    }
+                    None => {
+                        let pat = wildcard_variant(v.ident, v.fields);
+                        quote! { (#pat, #pat) => true, }
+                    }
+                });
+                quote! {
+                    match (self, other) {
+                        #(#arms)*
+                        _ => false,
+                    }
+                }
+            }
+        }
+    }
+
+    /// The body of the generated `Hash::hash`.
+    fn hash_body(&self) -> proc_macro2::TokenStream {
+        match self {
+            Identity::Struct(fields) => {
+                let accesses = fields.iter().map(IdField::access);
+                quote! { #(self.#accesses.hash(state);)* }
+            }
+            Identity::Enum(variants) => {
+                let arms = variants.iter().filter_map(|v| {
+                    let id = v.id.as_ref()?;
+                    let binding = format_ident!("__id");
+                    let pat = bind_variant(v.ident, v.fields, &id.member, &binding);
+                    let access = project_binding(&binding, &id.project);
+                    Some(quote! { #pat => #access.hash(state), })
+                });
+                // A catch-all is only needed when some variant carries no id.
+                let rest = if variants.iter().all(|v| v.id.is_some()) {
+                    quote!()
+                } else {
+                    quote!(_ => {})
+                };
+                quote! {
+                    std::mem::discriminant(self).hash(state);
+                    match self {
+                        #(#arms)*
+                        #rest
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether any identity field requested identity-based ordering via
+    /// `#[dervy(id, ord)]`. Ordering is only offered for structs.
+    fn wants_ord(&self) -> bool {
+        match self {
+            Identity::Struct(fields) => fields.iter().any(|f| f.ord),
+            Identity::Enum(_) => false,
+        }
+    }
+
+    /// Build the optional `PartialOrd`/`Ord` impls, delegating to the same
+    /// projected identity expression used by `eq`/`hash` so the orderings stay
+    /// consistent with equality.
+    fn ord_impls(
+        &self,
+        name: &Ident,
+        impl_generics: &syn::ImplGenerics,
+        ty_generics: &syn::TypeGenerics,
+        where_clause: Option<&syn::WhereClause>,
+    ) -> proc_macro2::TokenStream {
+        let Identity::Struct(fields) = self else {
+            return proc_macro2::TokenStream::new();
+        };
+        if !self.wants_ord() {
+            return proc_macro2::TokenStream::new();
+        }
+        let accesses = fields.iter().map(IdField::access).collect::<Vec<_>>();
+        quote! {
+            impl #impl_generics PartialOrd for #name #ty_generics #where_clause {
+                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                    Some(std::cmp::Ord::cmp(self, other))
+                }
+            }
+
+            impl #impl_generics Ord for #name #ty_generics #where_clause {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    (#(&self.#accesses,)*).cmp(&(#(&other.#accesses,)*))
+                }
+            }
+        }
+    }
+
+    /// Build the optional `dervy::Entity` impl.
+    ///
+    /// Only a single-member struct identity has a meaningful borrowable `Id`, so
+    /// a composite key or an enum gets no `dervy::Entity` impl. When the id field carries `newtype = Name`, a
+    /// `#[repr(transparent)]` wrapper is generated and used as the associated
+    /// `Id`, letting `id()` hand back a reference to it. The wrapper only
+    /// declares the entity's type parameters that actually appear in the id
+    /// field's type, so e.g. `Cache<K> { #[dervy(id, newtype = CacheId)] key: K }`
+    /// produces `struct CacheId<K>(pub K);` rather than referencing an
+    /// undeclared `K`, while a concretely-typed id field still produces a
+    /// plain, non-generic wrapper. `newtype` requires exactly one identity
+    /// field, so combining it with a composite key is a compile error rather
+    /// than a silently dropped wrapper.
+    fn entity_trait_impl(
+        &self,
+        name: &Ident,
+        generics: &syn::Generics,
+        impl_generics: &syn::ImplGenerics,
+        ty_generics: &syn::TypeGenerics,
+        where_clause: Option<&syn::WhereClause>,
+    ) -> proc_macro2::TokenStream {
+        let Identity::Struct(fields) = self else {
+            return proc_macro2::TokenStream::new();
+        };
+        if fields.len() > 1 {
+            if let Some(newtype) = fields.iter().find_map(|f| f.newtype.as_ref()) {
+                panic!(
+                    "`{name}`: `#[dervy(id, newtype = {newtype})]` is not supported with a \
+                     composite identity ({len} `#[dervy(id)]` fields); newtype requires exactly \
+                     one identity field",
+                    len = fields.len(),
+                );
+            }
+            return proc_macro2::TokenStream::new();
+        }
+        let [id] = fields.as_slice() else {
+            unreachable!("`find_id_field` never returns an empty `Identity::Struct`");
+        };
+        let member = &id.member;
+        let id_ty = id.ty;
+
+        match &id.newtype {
+            None => quote! {
+                impl #impl_generics dervy::Entity for #name #ty_generics #where_clause {
+                    type Id = #id_ty;
+                    fn id(&self) -> &Self::Id {
+                        &self.#member
+                    }
+                }
+            },
+            Some(newtype) => {
+                let newtype_generics = generics_used_in(generics, id_ty);
+                let (newtype_impl_generics, newtype_ty_generics, newtype_where) =
+                    newtype_generics.split_for_impl();
+                quote! {
+                    #[repr(transparent)]
+                    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+                    pub struct #newtype #newtype_impl_generics (pub #id_ty) #newtype_where;
+
+                    impl #impl_generics dervy::Entity for #name #ty_generics #where_clause {
+                        type Id = #newtype #newtype_ty_generics;
+                        fn id(&self) -> &Self::Id {
+                            // SAFETY: `#newtype` is `#[repr(transparent)]` over the id
+                            // field's type, so the two share a layout and a reference
+                            // to one is a valid reference to the other.
+                            unsafe {
+                                &*(&self.#member as *const #id_ty as *const #newtype #newtype_ty_generics)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The subset of `generics`'s type/const parameters that appear in `ty`,
+/// preserving declaration order. Used to give a generated newtype only the
+/// parameters it actually needs instead of either dropping them (leaving an
+/// undeclared type in the generated code) or carrying all of the entity's
+/// generics (which can leave unused ones on the wrapper).
+fn generics_used_in(generics: &syn::Generics, ty: &Type) -> syn::Generics {
+    let mut filtered = syn::Generics::default();
+    for param in &generics.params {
+        let mentioned = match param {
+            syn::GenericParam::Type(type_param) => type_mentions_ident(ty, &type_param.ident),
+            syn::GenericParam::Const(const_param) => type_mentions_ident(ty, &const_param.ident),
+            syn::GenericParam::Lifetime(_) => false,
+        };
+        if mentioned {
+            filtered.params.push(param.clone());
+        }
+    }
+    filtered
+}
+
+/// Whether `ty` mentions `ident`, either as a bare path (`T`), as a generic
+/// argument nested inside another type (`Wrapper<T>`), or as an array length
+/// (`[u8; N]`).
+fn type_mentions_ident(ty: &Type, ident: &Ident) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            if type_path.qself.is_none() && type_path.path.is_ident(ident) {
+                return true;
+            }
+            type_path.path.segments.iter().any(|segment| match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| {
+                    matches!(arg, syn::GenericArgument::Type(t) if type_mentions_ident(t, ident))
+                }),
+                _ => false,
+            })
+        }
+        Type::Reference(r) => type_mentions_ident(&r.elem, ident),
+        Type::Tuple(t) => t.elems.iter().any(|e| type_mentions_ident(e, ident)),
+        Type::Array(a) => {
+            type_mentions_ident(&a.elem, ident)
+                || matches!(&a.len, syn::Expr::Path(p) if p.path.is_ident(ident))
+        }
+        Type::Slice(s) => type_mentions_ident(&s.elem, ident),
+        Type::Paren(p) => type_mentions_ident(&p.elem, ident),
+        Type::Group(g) => type_mentions_ident(&g.elem, ident),
+        _ => false,
+    }
+}
+
+/// Apply an optional `project = field` modifier to a bound identity, turning
+/// `binding` into `binding.field`.
+fn project_binding(binding: &Ident, project: &Option<Ident>) -> proc_macro2::TokenStream {
+    match project {
+        Some(project) => quote!(#binding.#project),
+        None => quote!(#binding),
+    }
+}
+
+/// Pattern matching one enum variant, binding its identity member to `binding`
+/// and ignoring the rest.
+fn bind_variant(
+    variant: &Ident,
+    fields: &Fields,
+    member: &Member,
+    binding: &Ident,
+) -> proc_macro2::TokenStream {
+    match (fields, member) {
+        (Fields::Named(_), Member::Named(name)) => {
+            quote! { Self::#variant { #name: #binding, .. } }
+        }
+        (Fields::Unnamed(unnamed), Member::Unnamed(index)) => {
+            let pats = (0..unnamed.unnamed.len()).map(|i| {
+                if i as u32 == index.index {
+                    quote!(#binding)
+                } else {
+                    quote!(_)
+                }
+            });
+            quote! { Self::#variant(#(#pats),*) }
+        }
+        _ => unreachable!("variant shape and identity member always agree"),
+    }
+}
+
+/// Pattern matching one enum variant without binding anything, used for variants
+/// whose identity is just the discriminant.
+fn wildcard_variant(variant: &Ident, fields: &Fields) -> proc_macro2::TokenStream {
+    match fields {
+        Fields::Named(_) => quote! { Self::#variant { .. } },
+        Fields::Unnamed(_) => quote! { Self::#variant(..) },
+        Fields::Unit => quote! { Self::#variant },
+    }
+}
+
+fn find_id_field<'a>(data: &'a Data, name: &Ident) -> Identity<'a> {
+    match data {
+        Data::Struct(data_struct) => {
+            let mut id_fields = Vec::new();
+            for (index, field) in data_struct.fields.iter().enumerate() {
+                if let Some(opts) = parse_dervy_id_attribute(&field.attrs) {
+                    id_fields.push(IdField {
+                        member: field_member(field.ident.as_ref(), index),
+                        ty: &field.ty,
+                        newtype: opts.newtype,
+                        project: opts.project,
+                        ord: opts.ord,
+                    });
+                }
+            }
+            // Fall back to a field conventionally named `id`, so existing
+            // aggregates can adopt `dervy` without annotating every struct.
+            // Explicit `#[dervy(id)]` takes precedence when present.
+            if id_fields.is_empty() {
+                if let Some(field) = data_struct
+                    .fields
+                    .iter()
+                    .find(|field| field.ident.as_ref().is_some_and(|id| id == "id"))
+                {
+                    id_fields.push(IdField {
+                        member: field_member(field.ident.as_ref(), 0),
+                        ty: &field.ty,
+                        newtype: None,
+                        project: None,
+                        ord: false,
+                    });
+                }
+            }
+            if id_fields.is_empty() {
+                panic!("`{name}` has no `#[dervy(id)]` field and no field named `id`");
+            }
+            Identity::Struct(id_fields)
+        }
+        Data::Enum(data_enum) => {
+            let variants = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let id = variant.fields.iter().enumerate().find_map(|(index, field)| {
+                        parse_dervy_id_attribute(&field.attrs).map(|opts| {
+                            if opts.ord {
+                                panic!(
+                                    "`{name}::{}`: `#[dervy(id, ord)]` is not supported on enum \
+                                     variants; dervy does not order enum entities",
+                                    variant.ident,
+                                );
+                            }
+                            if opts.newtype.is_some() {
+                                panic!(
+                                    "`{name}::{}`: `#[dervy(id, newtype = ..)]` is not supported \
+                                     on enum variants; the `Entity` trait is only derived for \
+                                     single-field struct identities",
+                                    variant.ident,
+                                );
+                            }
+                            VariantId {
+                                member: field_member(field.ident.as_ref(), index),
+                                ty: &field.ty,
+                                project: opts.project,
+                            }
+                        })
+                    });
+                    IdVariant {
+                        ident: &variant.ident,
+                        fields: &variant.fields,
+                        id,
+                    }
+                })
+                .collect();
+            Identity::Enum(variants)
+        }
+        Data::Union(_) => panic!("dervy cannot derive Entity for unions"),
+    }
+}
+
+/// Build the [Member] addressing a field: its name when named, otherwise its
+/// declaration position.
+fn field_member(ident: Option<&Ident>, index: usize) -> Member {
+    match ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
+/// Parse a field's `#[dervy(..)]` attributes, returning the identity options
+/// (`newtype = Name`, `project = field`, and the `ord` flag) when the field is
+/// marked `id`.
+fn parse_dervy_id_attribute(attrs: &[Attribute]) -> Option<IdOptions> {
+    let mut is_id = false;
+    let mut newtype = None;
+    let mut project = None;
+    let mut ord = false;
+    for attr in attrs {
+        if !attr.path().is_ident("dervy") {
+            continue;
+        }
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                is_id = true;
+            } else if meta.path.is_ident("ord") {
+                ord = true;
+            } else if meta.path.is_ident("newtype") {
+                newtype = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("project") {
+                project = Some(meta.value()?.parse()?);
+            }
+            Ok(())
+        });
+    }
+    is_id.then_some(IdOptions {
+        newtype,
+        project,
+        ord,
+    })
+}
@@ -2,13 +2,39 @@
 //!
 //! [dervy] allows you to annotate your domain entities in order to derive
 //! implementations of [PartialEq], [Eq], and [Hash] that only consider identity for equality.
+//!
+//! `dervy` is a thin facade: the derive macro lives in `dervy-macros` (a
+//! `proc-macro = true` crate, which cannot export anything else) and the
+//! [Entity] trait it implements lives in `dervy-core`. Depend on `dervy`
+//! alone; the split is an implementation detail.
 
-use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident};
+pub use dervy_core::Entity;
 
 /// Derive [PartialEq], [Eq], and [Hash] for this entity type by considering
-/// the field annotated with `#[dervy(id)]`
+/// the field(s) annotated with `#[dervy(id)]`. For a struct with a single
+/// identity field, also implement [Entity] so callers can fetch an entity's
+/// identity without reaching into a type-specific field; composite
+/// identities and enums get no [Entity] impl.
+///
+/// If no field is annotated, a field literally named `id` is used instead, so
+/// existing aggregates can adopt `dervy` without annotating every struct.
+/// Annotate more than one field for a composite identity; equality and
+/// hashing then consider the annotated fields together, in declaration
+/// order. Tuple structs and enums are supported too: on an enum,
+/// `#[dervy(id)]` goes on a field inside a variant, and entities compare
+/// equal only when they're the same variant with equal identities.
+///
+/// # Modifiers
+///
+/// - `#[dervy(id, ord)]` additionally derives [PartialOrd]/[Ord] from the
+///   identity field(s), so entities can key a `BTreeMap`/`BTreeSet`. Structs
+///   only.
+/// - `#[dervy(id, project = field)]` compares and hashes
+///   `self.id_field.field` instead of `self.id_field`, for when the identity
+///   field is itself a wrapper type and only one of its fields should count.
+/// - `#[dervy(id, newtype = Name)]` additionally generates a
+///   `#[repr(transparent)]` wrapper struct `Name` and implements [Entity]
+///   with `Name` as the associated `Id`. Requires exactly one identity field.
 ///
 /// Example:
 ///
@@ -40,51 +66,4 @@ use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident};
 /// map.insert(ent1, true);
 /// assert!(map.contains_key(&ent2));
 /// ```
-#[proc_macro_derive(Entity, attributes(dervy))]
-pub fn derive_entity(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-    let name = &input.ident;
-
-    let id_field = find_id_field(&input.data);
-
-    let expanded = quote! {
-        impl PartialEq for #name {
-            fn eq(&self, other: &Self) -> bool {
-                self.#id_field == other.#id_field
-            }
-        }
-
-        impl Eq for #name {}
-
-        impl std::hash::Hash for #name {
-            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                self.#id_field.hash(state);
-            }
-        }
-    };
-
-    TokenStream::from(expanded)
-}
-
-fn find_id_field(data: &Data) -> Ident {
-    if let Data::Struct(data_struct) = data {
-        if let Fields::Named(fields) = &data_struct.fields {
-            for field in &fields.named {
-                if has_dervy_id_attribute(&field.attrs) {
-                    return field.ident.as_ref().unwrap().clone();
-                }
-            }
-        }
-    }
-    panic!("No field with #[dervy(id)] attribute found");
-}
-
-fn has_dervy_id_attribute(attrs: &[Attribute]) -> bool {
-    attrs.iter().any(|attr| {
-        attr.path().is_ident("dervy")
-            && attr
-                .parse_args::<Ident>()
-                .into_iter()
-                .any(|ident| ident == "id")
-    })
-}
+pub use dervy_macros::Entity;
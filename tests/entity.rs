@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use dervy::Entity;
+
+#[derive(Clone, Debug, dervy::Entity)]
+struct CompositeEntity {
+    #[dervy(id)]
+    tenant_id: u32,
+    #[dervy(id)]
+    order_id: u32,
+    note: &'static str,
+}
+
+#[test]
+fn composite_identity_ignores_non_id_fields() {
+    let a = CompositeEntity { tenant_id: 1, order_id: 1, note: "a" };
+    let mut b = a.clone();
+    b.note = "b";
+    assert_eq!(a, b);
+
+    let mut map = HashMap::new();
+    map.insert(a.clone(), true);
+    assert!(map.contains_key(&b));
+}
+
+#[test]
+fn composite_identity_requires_every_field_to_match() {
+    let a = CompositeEntity { tenant_id: 1, order_id: 1, note: "a" };
+    let different_order = CompositeEntity { tenant_id: 1, order_id: 2, note: "a" };
+    let different_tenant = CompositeEntity { tenant_id: 2, order_id: 1, note: "a" };
+    assert_ne!(a, different_order);
+    assert_ne!(a, different_tenant);
+}
+
+#[derive(Clone, Debug, dervy::Entity)]
+struct TupleEntity(#[dervy(id)] u32, bool);
+
+#[test]
+fn tuple_struct_identity_is_positional() {
+    let a = TupleEntity(1, true);
+    let b = TupleEntity(1, false);
+    let c = TupleEntity(2, true);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[derive(Clone, Debug, dervy::Entity)]
+enum Account {
+    Personal { #[dervy(id)] id: u64, name: &'static str },
+    Business(#[dervy(id)] u64),
+    Guest,
+}
+
+#[test]
+fn enum_identity_compares_within_a_variant() {
+    let a = Account::Personal { id: 1, name: "alice" };
+    let b = Account::Personal { id: 1, name: "bob" };
+    let c = Account::Personal { id: 2, name: "alice" };
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    let x = Account::Business(1);
+    let y = Account::Business(1);
+    assert_eq!(x, y);
+}
+
+#[test]
+fn enum_identity_never_matches_across_variants() {
+    let personal = Account::Personal { id: 1, name: "alice" };
+    let business = Account::Business(1);
+    assert_ne!(personal, business);
+
+    assert_eq!(Account::Guest, Account::Guest);
+    assert_ne!(Account::Guest, Account::Business(1));
+}
+
+#[derive(Clone, Debug, dervy::Entity)]
+struct Wrapped {
+    #[dervy(id, newtype = WrappedId)]
+    key: u32,
+    payload: Vec<u8>,
+}
+
+#[test]
+fn newtype_wraps_the_id_field_and_is_returned_by_id() {
+    let entity = Wrapped { key: 42, payload: vec![1, 2, 3] };
+    let id: &WrappedId = entity.id();
+    assert_eq!(id.0, 42);
+}
+
+// Deliberately does not derive PartialEq/Eq/Hash/Ord: `project` exists so the
+// identity field doesn't need to implement them, only its projected subfield.
+#[derive(Clone, Debug)]
+struct Key {
+    uuid: u32,
+    cached_display: &'static str,
+}
+
+#[derive(Clone, Debug, dervy::Entity)]
+struct Projected {
+    #[dervy(id, ord, project = uuid)]
+    key: Key,
+}
+
+#[test]
+fn project_compares_only_the_projected_subfield() {
+    let a = Projected { key: Key { uuid: 1, cached_display: "a" } };
+    let b = Projected { key: Key { uuid: 1, cached_display: "b" } };
+    assert_eq!(a, b);
+
+    let mut map = HashMap::new();
+    map.insert(a.clone(), true);
+    assert!(map.contains_key(&b));
+}
+
+#[test]
+fn ord_is_consistent_with_the_projected_identity() {
+    let mut entities = vec![
+        Projected { key: Key { uuid: 3, cached_display: "c" } },
+        Projected { key: Key { uuid: 1, cached_display: "a" } },
+        Projected { key: Key { uuid: 2, cached_display: "b" } },
+    ];
+    entities.sort();
+    let uuids: Vec<u32> = entities.iter().map(|e| e.key.uuid).collect();
+    assert_eq!(uuids, vec![1, 2, 3]);
+}
+
+#[derive(Clone, Debug, dervy::Entity)]
+struct ConventionalId {
+    id: u32,
+    label: &'static str,
+}
+
+#[test]
+fn falls_back_to_a_field_named_id_when_unannotated() {
+    let a = ConventionalId { id: 1, label: "a" };
+    let mut b = a.clone();
+    b.label = "b";
+    assert_eq!(a, b);
+
+    let c = ConventionalId { id: 2, label: "a" };
+    assert_ne!(a, c);
+}